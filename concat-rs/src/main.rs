@@ -1,7 +1,10 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
 use std::env;
 use std::fs::{self, File};
 use std::io::{self, Write, BufWriter};
 use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 #[derive(Default)]
 struct Options {
@@ -11,48 +14,616 @@ struct Options {
     hidden: bool,
     tree: bool,
     text: bool,
+    gitignore: bool,
+    sort: Option<SortKey>,
+    groups: Vec<(String, String)>,
+    max_bytes: Option<u64>,
+    max_tokens: Option<u64>,
+    on_overflow: OverflowMode,
+    line_endings: Option<LineEndingStyle>,
+    strip_trailing_ws: bool,
+    ensure_final_newline: bool,
+    tabs_to_spaces: Option<usize>,
     output: Option<String>,
     inputs: Vec<String>,
 }
 
+/// Target line-ending style for `--line-endings`. `Preserve` keeps each
+/// file's own dominant ending but still flattens any stray mixed-in ones.
+#[derive(Clone, Copy)]
+enum LineEndingStyle {
+    Preserve,
+    Lf,
+    Crlf,
+}
+
+fn parse_line_ending_style(value: &str) -> Option<LineEndingStyle> {
+    match value {
+        "preserve" => Some(LineEndingStyle::Preserve),
+        "lf" => Some(LineEndingStyle::Lf),
+        "crlf" => Some(LineEndingStyle::Crlf),
+        _ => None,
+    }
+}
+
+/// What to do with files once `--max-bytes`/`--max-tokens` is exhausted.
+#[derive(Clone, Copy, Default)]
+enum OverflowMode {
+    #[default]
+    Skip,
+    Truncate,
+}
+
+fn parse_overflow_mode(value: &str) -> Option<OverflowMode> {
+    match value {
+        "skip" => Some(OverflowMode::Skip),
+        "truncate" => Some(OverflowMode::Truncate),
+        _ => None,
+    }
+}
+
+/// Ordering applied to files when no `--group` bucketing is in effect, or to
+/// a bucket's leftover ungrouped files.
+#[derive(Clone, Copy)]
+enum SortKey {
+    Path,
+    Name,
+    Mtime,
+    Size,
+}
+
+fn parse_sort_key(value: &str) -> Option<SortKey> {
+    match value {
+        "path" => Some(SortKey::Path),
+        "name" => Some(SortKey::Name),
+        "mtime" => Some(SortKey::Mtime),
+        "size" => Some(SortKey::Size),
+        _ => None,
+    }
+}
+
+/// Extensions always treated as images and embedded as base64 data URLs.
+const IMAGE_EXTS: &[&str] = &["png", "jpg", "jpeg", "webp", "gif", "bmp", "ico"];
+
+/// How many leading bytes of a file to inspect when sniffing for binary content.
+const SNIFF_LEN: usize = 8000;
+
 fn print_help() {
     println!("concat - merge file contents\n");
     println!("Usage: concat [options] [files or directories]\n");
     println!("Options:");
     println!("  -x, --ext EXT          Filter by extension (may repeat)");
-    println!("  -i, --include PATTERN  Include glob pattern (may repeat)");
-    println!("  -e, --exclude PATTERN  Exclude glob pattern (may repeat)");
+    println!("  -i, --include PATTERN  Include glob pattern: ?, *, **, [..], {{a,b}} (may repeat)");
+    println!("  -e, --exclude PATTERN  Exclude glob pattern: ?, *, **, [..], {{a,b}} (may repeat)");
     println!("      --hidden           Include hidden files");
     println!("  -t, --tree             Include directory tree in output");
     println!("      --text             Output plain text (default XML)");
+    println!("      --no-ignore        Do not respect .gitignore files");
+    println!("      --gitignore        Respect .gitignore files (default)");
+    println!("      --sort KEY         Sort files: path, name, mtime, size");
+    println!("      --group P=>T       Bucket files matching regex P into group named T (may repeat)");
+    println!("      --max-bytes N      Stop accepting file content past N content bytes");
+    println!("      --max-tokens N     Stop accepting file content past N approx. tokens (chars/4)");
+    println!("      --on-overflow MODE What to do past the budget: skip (default) or truncate");
+    println!("      --line-endings EOL Normalize line endings: lf, crlf, preserve");
+    println!("      --strip-trailing-ws Strip trailing whitespace from every line");
+    println!("      --ensure-final-newline Append a newline if content does not end with one");
+    println!("      --tabs-to-spaces N Replace tabs with N spaces");
     println!("  -o, --output FILE      Output filename");
     println!("  -h, --help             Show this help");
 }
 
-fn matches_pattern(pattern: &str, text: &str) -> bool {
-    if pattern == "*" {
+/// One piece of a compiled glob segment (the part of a pattern between `/`
+/// separators).
+enum GlobToken {
+    Literal(char),
+    AnyChar,
+    Star,
+    Class { negate: bool, items: Vec<ClassItem> },
+}
+
+enum ClassItem {
+    Char(char),
+    Range(char, char),
+}
+
+/// Compile a single path segment (no `/` in it) into tokens: `?` becomes
+/// `AnyChar`, `*` becomes `Star`, and `[...]`/`[!...]` becomes a `Class`.
+fn compile_segment(segment: &str) -> Vec<GlobToken> {
+    let chars: Vec<char> = segment.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '?' => {
+                tokens.push(GlobToken::AnyChar);
+                i += 1;
+            }
+            '*' => {
+                tokens.push(GlobToken::Star);
+                i += 1;
+            }
+            '[' => {
+                if let Some(end) = chars[i + 1..].iter().position(|&c| c == ']') {
+                    let end = i + 1 + end;
+                    let mut body = &chars[i + 1..end][..];
+                    let negate = matches!(body.first(), Some('!'));
+                    if negate {
+                        body = &body[1..];
+                    }
+                    let mut items = Vec::new();
+                    let mut j = 0;
+                    while j < body.len() {
+                        if j + 2 < body.len() && body[j + 1] == '-' {
+                            items.push(ClassItem::Range(body[j], body[j + 2]));
+                            j += 3;
+                        } else {
+                            items.push(ClassItem::Char(body[j]));
+                            j += 1;
+                        }
+                    }
+                    tokens.push(GlobToken::Class { negate, items });
+                    i = end + 1;
+                } else {
+                    tokens.push(GlobToken::Literal('['));
+                    i += 1;
+                }
+            }
+            c => {
+                tokens.push(GlobToken::Literal(c));
+                i += 1;
+            }
+        }
+    }
+    tokens
+}
+
+/// Backtracking two-pointer match of compiled segment tokens against a
+/// segment of literal text (no `/` in either side).
+fn match_segment_tokens(tokens: &[GlobToken], text: &[char]) -> bool {
+    match tokens.first() {
+        None => text.is_empty(),
+        Some(GlobToken::Star) => (0..=text.len()).any(|i| match_segment_tokens(&tokens[1..], &text[i..])),
+        Some(GlobToken::AnyChar) => {
+            !text.is_empty() && match_segment_tokens(&tokens[1..], &text[1..])
+        }
+        Some(GlobToken::Literal(c)) => {
+            !text.is_empty() && text[0] == *c && match_segment_tokens(&tokens[1..], &text[1..])
+        }
+        Some(GlobToken::Class { negate, items }) => {
+            if text.is_empty() {
+                return false;
+            }
+            let c = text[0];
+            let mut hit = items.iter().any(|item| match item {
+                ClassItem::Char(x) => *x == c,
+                ClassItem::Range(a, b) => *a <= c && c <= *b,
+            });
+            if *negate {
+                hit = !hit;
+            }
+            hit && match_segment_tokens(&tokens[1..], &text[1..])
+        }
+    }
+}
+
+fn match_segment(pattern: &str, text: &str) -> bool {
+    let tokens = compile_segment(pattern);
+    let chars: Vec<char> = text.chars().collect();
+    match_segment_tokens(&tokens, &chars)
+}
+
+/// Match a `/`-free sequence of pattern segments (where `**` may stand for
+/// zero or more whole path segments) against the path's own segments.
+fn match_path_segments(pat_segs: &[&str], path_segs: &[&str]) -> bool {
+    match pat_segs.first() {
+        None => path_segs.is_empty(),
+        Some(&"**") => {
+            match_path_segments(&pat_segs[1..], path_segs)
+                || (!path_segs.is_empty() && match_path_segments(pat_segs, &path_segs[1..]))
+        }
+        Some(seg) => {
+            !path_segs.is_empty()
+                && match_segment(seg, path_segs[0])
+                && match_path_segments(&pat_segs[1..], &path_segs[1..])
+        }
+    }
+}
+
+/// Expand `{a,b,c}` brace alternation into separate patterns, one per
+/// alternative, expanding repeatedly so multiple brace groups in one
+/// pattern all get their cartesian product of combinations.
+fn expand_braces(pattern: &str) -> Vec<String> {
+    if let Some(open) = pattern.find('{') {
+        if let Some(rel_close) = pattern[open..].find('}') {
+            let close = open + rel_close;
+            let prefix = &pattern[..open];
+            let body = &pattern[open + 1..close];
+            let suffix = &pattern[close + 1..];
+            let mut out = Vec::new();
+            for alt in body.split(',') {
+                for rest in expand_braces(suffix) {
+                    out.push(format!("{}{}{}", prefix, alt, rest));
+                }
+            }
+            return out.into_iter().flat_map(|p| expand_braces(&p)).collect();
+        }
+    }
+    vec![pattern.to_string()]
+}
+
+/// Full glob matcher: expands `{a,b,c}` brace alternation, splits both the
+/// pattern and the path on `/`, then matches segment-by-segment with `**`
+/// consuming whole path segments and `?`/`*`/`[...]` matching within a
+/// single segment.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    expand_braces(pattern).iter().any(|alt| {
+        let pat_segs: Vec<&str> = alt.split('/').collect();
+        let path_segs: Vec<&str> = text.split('/').collect();
+        match_path_segments(&pat_segs, &path_segs)
+    })
+}
+
+/// The small regex subset `--group` needs: literals, `.`, `\d`/`\D`,
+/// `[...]`/`[!...]` classes, `(...)` capturing groups, and `*`/`+`/`?`
+/// quantifiers on the atom right before them.
+enum RegexAtomKind {
+    Literal(char),
+    Any,
+    Digit,
+    NotDigit,
+    Class { negate: bool, items: Vec<ClassItem> },
+    Group(Vec<RegexAtom>, usize),
+}
+
+enum RegexQuant {
+    One,
+    Star,
+    Plus,
+    Question,
+}
+
+struct RegexAtom {
+    kind: RegexAtomKind,
+    quant: RegexQuant,
+}
+
+/// Compile `pattern`, assigning capture-group indices left to right starting
+/// at 1, and return the atoms alongside the total number of groups.
+fn compile_regex(pattern: &str) -> (Vec<RegexAtom>, usize) {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut pos = 0;
+    let mut group_count = 0;
+    let atoms = parse_regex_atoms(&chars, &mut pos, &mut group_count);
+    (atoms, group_count)
+}
+
+fn parse_regex_atoms(chars: &[char], pos: &mut usize, group_count: &mut usize) -> Vec<RegexAtom> {
+    let mut atoms = Vec::new();
+    while *pos < chars.len() && chars[*pos] != ')' {
+        let kind = parse_regex_atom_kind(chars, pos, group_count);
+        let quant = match chars.get(*pos) {
+            Some('*') => {
+                *pos += 1;
+                RegexQuant::Star
+            }
+            Some('+') => {
+                *pos += 1;
+                RegexQuant::Plus
+            }
+            Some('?') => {
+                *pos += 1;
+                RegexQuant::Question
+            }
+            _ => RegexQuant::One,
+        };
+        atoms.push(RegexAtom { kind, quant });
+    }
+    atoms
+}
+
+fn parse_regex_atom_kind(
+    chars: &[char],
+    pos: &mut usize,
+    group_count: &mut usize,
+) -> RegexAtomKind {
+    match chars[*pos] {
+        '(' => {
+            *pos += 1;
+            *group_count += 1;
+            let idx = *group_count;
+            let inner = parse_regex_atoms(chars, pos, group_count);
+            if chars.get(*pos) == Some(&')') {
+                *pos += 1;
+            }
+            RegexAtomKind::Group(inner, idx)
+        }
+        '.' => {
+            *pos += 1;
+            RegexAtomKind::Any
+        }
+        '[' => {
+            *pos += 1;
+            let negate = chars.get(*pos) == Some(&'!');
+            if negate {
+                *pos += 1;
+            }
+            let mut items = Vec::new();
+            while *pos < chars.len() && chars[*pos] != ']' {
+                if *pos + 2 < chars.len() && chars[*pos + 1] == '-' && chars[*pos + 2] != ']' {
+                    items.push(ClassItem::Range(chars[*pos], chars[*pos + 2]));
+                    *pos += 3;
+                } else {
+                    items.push(ClassItem::Char(chars[*pos]));
+                    *pos += 1;
+                }
+            }
+            if *pos < chars.len() {
+                *pos += 1;
+            }
+            RegexAtomKind::Class { negate, items }
+        }
+        '\\' => {
+            *pos += 1;
+            let c = chars.get(*pos).copied().unwrap_or('\\');
+            *pos += 1;
+            match c {
+                'd' => RegexAtomKind::Digit,
+                'D' => RegexAtomKind::NotDigit,
+                other => RegexAtomKind::Literal(other),
+            }
+        }
+        c => {
+            *pos += 1;
+            RegexAtomKind::Literal(c)
+        }
+    }
+}
+
+type Captures = Vec<Option<(usize, usize)>>;
+
+fn match_seq(
+    atoms: &[RegexAtom],
+    text: &[char],
+    pos: usize,
+    caps: &mut Captures,
+    cont: &mut dyn FnMut(usize, &mut Captures) -> bool,
+) -> bool {
+    match atoms.split_first() {
+        None => cont(pos, caps),
+        Some((atom, rest)) => {
+            match_atom(atom, text, pos, caps, &mut |p, caps| {
+                match_seq(rest, text, p, caps, cont)
+            })
+        }
+    }
+}
+
+fn match_atom(
+    atom: &RegexAtom,
+    text: &[char],
+    pos: usize,
+    caps: &mut Captures,
+    cont: &mut dyn FnMut(usize, &mut Captures) -> bool,
+) -> bool {
+    match atom.quant {
+        RegexQuant::One => match_kind(&atom.kind, text, pos, caps, cont),
+        RegexQuant::Question => {
+            if match_kind(&atom.kind, text, pos, caps, cont) {
+                return true;
+            }
+            cont(pos, caps)
+        }
+        RegexQuant::Star => match_repeat(&atom.kind, text, pos, caps, cont, 0),
+        RegexQuant::Plus => match_repeat(&atom.kind, text, pos, caps, cont, 1),
+    }
+}
+
+/// Greedily repeat `kind`, backtracking to fewer repetitions (down to `min`)
+/// if the rest of the pattern (`cont`) can't follow.
+fn match_repeat(
+    kind: &RegexAtomKind,
+    text: &[char],
+    pos: usize,
+    caps: &mut Captures,
+    cont: &mut dyn FnMut(usize, &mut Captures) -> bool,
+    min: usize,
+) -> bool {
+    let matched_more = match_kind(kind, text, pos, caps, &mut |next, caps| {
+        if next == pos {
+            return false;
+        }
+        match_repeat(kind, text, next, caps, cont, min.saturating_sub(1))
+    });
+    if matched_more {
         return true;
     }
-    let mut rest = text;
-    let mut first = true;
-    for part in pattern.split('*') {
-        if part.is_empty() {
-            continue;
+    if min == 0 {
+        cont(pos, caps)
+    } else {
+        false
+    }
+}
+
+fn match_kind(
+    kind: &RegexAtomKind,
+    text: &[char],
+    pos: usize,
+    caps: &mut Captures,
+    cont: &mut dyn FnMut(usize, &mut Captures) -> bool,
+) -> bool {
+    match kind {
+        RegexAtomKind::Literal(c) => pos < text.len() && text[pos] == *c && cont(pos + 1, caps),
+        RegexAtomKind::Any => pos < text.len() && cont(pos + 1, caps),
+        RegexAtomKind::Digit => {
+            pos < text.len() && text[pos].is_ascii_digit() && cont(pos + 1, caps)
+        }
+        RegexAtomKind::NotDigit => {
+            pos < text.len() && !text[pos].is_ascii_digit() && cont(pos + 1, caps)
         }
-        if let Some(idx) = rest.find(part) {
-            if first && !pattern.starts_with('*') && idx != 0 {
+        RegexAtomKind::Class { negate, items } => {
+            if pos >= text.len() {
                 return false;
             }
-            rest = &rest[idx + part.len()..];
+            let c = text[pos];
+            let mut hit = items.iter().any(|item| match item {
+                ClassItem::Char(x) => *x == c,
+                ClassItem::Range(a, b) => *a <= c && c <= *b,
+            });
+            if *negate {
+                hit = !hit;
+            }
+            hit && cont(pos + 1, caps)
+        }
+        RegexAtomKind::Group(inner, idx) => {
+            let start = pos;
+            let idx = *idx;
+            match_seq(inner, text, pos, caps, &mut |end, caps| {
+                let prev = caps[idx - 1];
+                caps[idx - 1] = Some((start, end));
+                if cont(end, caps) {
+                    true
+                } else {
+                    caps[idx - 1] = prev;
+                    false
+                }
+            })
+        }
+    }
+}
+
+/// Match `pattern` against the whole of `text` (implicit `^...$` anchors),
+/// returning each capture group's substring (1-indexed, `None` if it didn't
+/// participate) on success.
+fn regex_full_match(pattern: &str, text: &str) -> Option<Vec<Option<String>>> {
+    let (atoms, group_count) = compile_regex(pattern);
+    let chars: Vec<char> = text.chars().collect();
+    let mut caps: Captures = vec![None; group_count];
+    let matched = match_seq(&atoms, &chars, 0, &mut caps, &mut |end, _| end == chars.len());
+    if matched {
+        Some(
+            caps.iter()
+                .map(|c| c.map(|(s, e)| chars[s..e].iter().collect()))
+                .collect(),
+        )
+    } else {
+        None
+    }
+}
+
+/// Substitute `$1`, `$2`, ... in `template` with the corresponding capture
+/// group, leaving unmatched or out-of-range references empty.
+fn render_template(template: &str, caps: &[Option<String>]) -> String {
+    let chars: Vec<char> = template.chars().collect();
+    let mut out = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] == '$' && chars.get(i + 1).is_some_and(|c| c.is_ascii_digit()) {
+            let mut j = i + 1;
+            while chars.get(j).is_some_and(|c| c.is_ascii_digit()) {
+                j += 1;
+            }
+            let num: usize = chars[i + 1..j].iter().collect::<String>().parse().unwrap_or(0);
+            if num >= 1 {
+                if let Some(Some(val)) = caps.get(num - 1) {
+                    out.push_str(val);
+                }
+            }
+            i = j;
         } else {
-            return false;
+            out.push(chars[i]);
+            i += 1;
         }
-        first = false;
     }
-    if !pattern.ends_with('*') && !rest.is_empty() {
-        return false;
+    out
+}
+
+/// Order files either by a flat `--sort` key, or by bucketing them per
+/// `--group PATTERN=>TEMPLATE` rules: files matching a group's regex land in
+/// the bucket named by its rendered template, ordered within the bucket by
+/// the pattern's first capture (numerically if possible, else lexically);
+/// files matching no group fall back to a single `--sort`-ordered bucket.
+struct Bucket {
+    name: Option<String>,
+    files: Vec<PathBuf>,
+}
+
+fn sort_files(files: &mut [PathBuf], sort: Option<SortKey>) {
+    match sort {
+        None => {}
+        Some(SortKey::Path) => files.sort(),
+        Some(SortKey::Name) => files.sort_by(|a, b| {
+            a.file_name()
+                .unwrap_or_default()
+                .cmp(b.file_name().unwrap_or_default())
+        }),
+        Some(SortKey::Mtime) => files.sort_by_key(|f| {
+            fs::metadata(f)
+                .and_then(|m| m.modified())
+                .unwrap_or(SystemTime::UNIX_EPOCH)
+        }),
+        Some(SortKey::Size) => files.sort_by_key(|f| fs::metadata(f).map(|m| m.len()).unwrap_or(0)),
+    }
+}
+
+fn compare_order_keys(a: &str, b: &str) -> std::cmp::Ordering {
+    match (a.parse::<u64>(), b.parse::<u64>()) {
+        (Ok(x), Ok(y)) => x.cmp(&y),
+        _ => a.cmp(b),
     }
-    true
+}
+
+fn build_buckets(files: &[PathBuf], groups: &[(String, String)], sort: Option<SortKey>) -> Vec<Bucket> {
+    if groups.is_empty() {
+        let mut sorted = files.to_vec();
+        sort_files(&mut sorted, sort);
+        return vec![Bucket {
+            name: None,
+            files: sorted,
+        }];
+    }
+    let mut named: BTreeMap<String, Vec<(String, PathBuf)>> = BTreeMap::new();
+    let mut ungrouped = Vec::new();
+    for f in files {
+        let file_name = f.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        let mut matched = false;
+        for (pattern, template) in groups {
+            if let Some(caps) = regex_full_match(pattern, file_name) {
+                let bucket_name = render_template(template, &caps);
+                let order_key = caps
+                    .first()
+                    .and_then(|c| c.clone())
+                    .unwrap_or_else(|| file_name.to_string());
+                named
+                    .entry(bucket_name)
+                    .or_default()
+                    .push((order_key, f.clone()));
+                matched = true;
+                break;
+            }
+        }
+        if !matched {
+            ungrouped.push(f.clone());
+        }
+    }
+    let mut buckets: Vec<Bucket> = named
+        .into_iter()
+        .map(|(name, mut items)| {
+            items.sort_by(|a, b| compare_order_keys(&a.0, &b.0).then_with(|| a.1.cmp(&b.1)));
+            Bucket {
+                name: Some(name),
+                files: items.into_iter().map(|(_, p)| p).collect(),
+            }
+        })
+        .collect();
+    if !ungrouped.is_empty() {
+        sort_files(&mut ungrouped, sort);
+        buckets.push(Bucket {
+            name: None,
+            files: ungrouped,
+        });
+    }
+    buckets
 }
 
 fn should_include(path: &Path, opts: &Options) -> bool {
@@ -74,24 +645,107 @@ fn should_include(path: &Path, opts: &Options) -> bool {
     }
     let path_str = path.to_string_lossy();
     if !opts.includes.is_empty()
-        && !opts
-            .includes
-            .iter()
-            .any(|p| matches_pattern(p, &path_str))
+        && !opts.includes.iter().any(|p| glob_match(p, &path_str))
     {
         return false;
     }
-    if opts
-        .excludes
-        .iter()
-        .any(|p| matches_pattern(p, &path_str))
-    {
+    if opts.excludes.iter().any(|p| glob_match(p, &path_str)) {
         return false;
     }
     true
 }
 
-fn gather_files(path: &Path, files: &mut Vec<PathBuf>, opts: &Options) -> io::Result<()> {
+/// A single compiled line from a `.gitignore` file, scoped to the directory
+/// it was found in so later, deeper `.gitignore` files can override it.
+struct IgnorePattern {
+    glob: String,
+    negate: bool,
+    dir_only: bool,
+    anchored: bool,
+    base: PathBuf,
+}
+
+/// Parse the `.gitignore` in `dir`, if any, into compiled patterns. Missing
+/// or unreadable files simply contribute no patterns.
+fn parse_gitignore(dir: &Path) -> Vec<IgnorePattern> {
+    let content = match fs::read_to_string(dir.join(".gitignore")) {
+        Ok(c) => c,
+        Err(_) => return Vec::new(),
+    };
+    let mut patterns = Vec::new();
+    for raw in content.lines() {
+        let line = raw.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut pat = line;
+        let negate = if let Some(stripped) = pat.strip_prefix('!') {
+            pat = stripped;
+            true
+        } else {
+            false
+        };
+        let dir_only = pat.ends_with('/');
+        if dir_only {
+            pat = &pat[..pat.len() - 1];
+        }
+        let anchored = pat.starts_with('/');
+        if anchored {
+            pat = &pat[1..];
+        }
+        if pat.is_empty() {
+            continue;
+        }
+        patterns.push(IgnorePattern {
+            glob: pat.to_string(),
+            negate,
+            dir_only,
+            anchored,
+            base: dir.to_path_buf(),
+        });
+    }
+    patterns
+}
+
+/// Test a single compiled pattern against `path`, relative to the directory
+/// its `.gitignore` lives in.
+fn ignore_pattern_matches(pattern: &IgnorePattern, path: &Path, is_dir: bool) -> bool {
+    if pattern.dir_only && !is_dir {
+        return false;
+    }
+    let rel = match path.strip_prefix(&pattern.base) {
+        Ok(r) => r,
+        Err(_) => return false,
+    };
+    let rel_str = rel.to_string_lossy();
+    if pattern.anchored || pattern.glob.contains('/') {
+        glob_match(&pattern.glob, &rel_str)
+    } else {
+        glob_match(&format!("**/{}", pattern.glob), &rel_str)
+    }
+}
+
+/// Walk the stack of `.gitignore` pattern sets from shallowest to deepest,
+/// letting the last match (at any depth) win, matching standard gitignore
+/// semantics for negation and overrides.
+fn is_ignored(stack: &[Vec<IgnorePattern>], path: &Path, is_dir: bool) -> bool {
+    let mut ignored = false;
+    for patterns in stack {
+        for pattern in patterns {
+            if ignore_pattern_matches(pattern, path, is_dir) {
+                ignored = !pattern.negate;
+            }
+        }
+    }
+    ignored
+}
+
+fn gather_files(
+    path: &Path,
+    files: &mut Vec<PathBuf>,
+    opts: &Options,
+    ignore_stack: &mut Vec<Vec<IgnorePattern>>,
+) -> io::Result<()> {
     if path.is_file() {
         if should_include(path, opts) {
             files.push(path.to_path_buf());
@@ -99,17 +753,27 @@ fn gather_files(path: &Path, files: &mut Vec<PathBuf>, opts: &Options) -> io::Re
         return Ok(());
     }
     if path.is_dir() {
+        if opts.gitignore {
+            if path.file_name().and_then(|n| n.to_str()) == Some(".git") {
+                return Ok(());
+            }
+            ignore_stack.push(parse_gitignore(path));
+        }
         for entry in fs::read_dir(path)? {
             let entry = entry?;
             let p = entry.path();
+            if opts.gitignore && is_ignored(ignore_stack, &p, p.is_dir()) {
+                continue;
+            }
             if p.is_dir() {
-                gather_files(&p, files, opts)?;
-            } else if p.is_file() {
-                if should_include(&p, opts) {
-                    files.push(p);
-                }
+                gather_files(&p, files, opts, ignore_stack)?;
+            } else if p.is_file() && should_include(&p, opts) {
+                files.push(p);
             }
         }
+        if opts.gitignore {
+            ignore_stack.pop();
+        }
     }
     Ok(())
 }
@@ -139,8 +803,403 @@ fn escape_xml(text: &str) -> String {
         .replace('>', "&gt;")
 }
 
+/// What a gathered file turned out to contain, decided by extension sniffing
+/// and a scan for NUL bytes since `fs::read_to_string` would otherwise abort
+/// the whole run on the first non-UTF-8 file.
+enum FileContent {
+    Text(String),
+    Binary { bytes: Vec<u8>, mime: &'static str },
+}
+
+/// Guess a MIME type from a lowercased extension, defaulting to a generic
+/// binary type for anything not in `IMAGE_EXTS`.
+fn mime_for_ext(ext: &str) -> &'static str {
+    match ext {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "webp" => "image/webp",
+        "gif" => "image/gif",
+        "bmp" => "image/bmp",
+        "ico" => "image/x-icon",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Read a file, classifying it as text or binary rather than failing the
+/// whole run when it isn't valid UTF-8.
+fn read_file_content(path: &Path) -> io::Result<FileContent> {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default();
+    let is_image = IMAGE_EXTS.contains(&ext.as_str());
+    let bytes = fs::read(path)?;
+    if is_image || looks_binary(&bytes) {
+        Ok(FileContent::Binary {
+            mime: mime_for_ext(&ext),
+            bytes,
+        })
+    } else {
+        match String::from_utf8(bytes) {
+            Ok(s) => Ok(FileContent::Text(s)),
+            Err(e) => Ok(FileContent::Binary {
+                mime: mime_for_ext(&ext),
+                bytes: e.into_bytes(),
+            }),
+        }
+    }
+}
+
+/// Sniff the first `SNIFF_LEN` bytes for a NUL byte, a cheap but reliable
+/// signal that a file is not meant to be read as text.
+fn looks_binary(bytes: &[u8]) -> bool {
+    bytes.iter().take(SNIFF_LEN).any(|&b| b == 0)
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Standard (RFC 4648) base64 encoding with `=` padding, hand-rolled since
+/// the rest of the tool avoids external crates.
+fn base64_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b0 = chunk[0];
+        let b1 = *chunk.get(1).unwrap_or(&0);
+        let b2 = *chunk.get(2).unwrap_or(&0);
+        out.push(BASE64_ALPHABET[(b0 >> 2) as usize] as char);
+        out.push(BASE64_ALPHABET[(((b0 & 0x03) << 4) | (b1 >> 4)) as usize] as char);
+        if chunk.len() > 1 {
+            out.push(BASE64_ALPHABET[(((b1 & 0x0f) << 2) | (b2 >> 6)) as usize] as char);
+        } else {
+            out.push('=');
+        }
+        if chunk.len() > 2 {
+            out.push(BASE64_ALPHABET[(b2 & 0x3f) as usize] as char);
+        } else {
+            out.push('=');
+        }
+    }
+    out
+}
+
+/// A file's content after the `--max-bytes`/`--max-tokens` budget has been
+/// applied: included as-is, truncated to fit, or dropped entirely.
+enum PlannedContent {
+    Text(String),
+    Binary { bytes: Vec<u8>, mime: &'static str },
+    Skipped,
+}
+
+enum ManifestStatus {
+    Included,
+    Truncated,
+    Skipped,
+}
+
+/// One line of the `--max-bytes`/`--max-tokens` manifest: what made the cut,
+/// what didn't, and why.
+struct ManifestEntry {
+    path: PathBuf,
+    bytes: u64,
+    tokens: u64,
+    status: ManifestStatus,
+}
+
+/// Rough chars/4 token estimate, the same heuristic the manifest reports.
+fn estimate_tokens(chars: u64) -> u64 {
+    chars.div_ceil(4)
+}
+
+fn budget_exceeded(
+    total_bytes: u64,
+    total_tokens: u64,
+    add_bytes: u64,
+    add_tokens: u64,
+    max_bytes: Option<u64>,
+    max_tokens: Option<u64>,
+) -> bool {
+    max_bytes.is_some_and(|m| total_bytes + add_bytes > m)
+        || max_tokens.is_some_and(|m| total_tokens + add_tokens > m)
+}
+
+/// Truncate a text file to whichever budget is tightest, given what's
+/// already been spent. Byte and char counts diverge for non-ASCII text, so
+/// this walks char by char (never splitting a multi-byte char) and stops as
+/// soon as either the byte or the token budget would be exceeded.
+fn truncate_to_budget(
+    text: &str,
+    total_bytes: u64,
+    total_tokens: u64,
+    max_bytes: Option<u64>,
+    max_tokens: Option<u64>,
+) -> String {
+    let byte_budget = max_bytes.map(|m| m.saturating_sub(total_bytes));
+    let char_budget = max_tokens.map(|m| m.saturating_sub(total_tokens) * 4);
+    let mut result = String::new();
+    let mut bytes_used: u64 = 0;
+    for (chars_used, c) in (0u64..).zip(text.chars()) {
+        let char_len = c.len_utf8() as u64;
+        if byte_budget.is_some_and(|b| bytes_used + char_len > b) {
+            break;
+        }
+        if char_budget.is_some_and(|b| chars_used + 1 > b) {
+            break;
+        }
+        result.push(c);
+        bytes_used += char_len;
+    }
+    result
+}
+
+/// A gathered file alongside its optional group bucket name.
+type GroupedFile<C> = (Option<String>, PathBuf, C);
+
+/// Apply the `--max-bytes`/`--max-tokens` budget to an ordered sequence of
+/// gathered files, producing both the manifest (what was included,
+/// truncated, or skipped) and the content actually to be written. Once the
+/// budget is hit, every later file is skipped — also after a file that was
+/// truncated to fit, since there's no room left after it.
+fn apply_budget(
+    ordered: Vec<GroupedFile<FileContent>>,
+    max_bytes: Option<u64>,
+    max_tokens: Option<u64>,
+    on_overflow: OverflowMode,
+) -> (Vec<GroupedFile<PlannedContent>>, Vec<ManifestEntry>) {
+    let mut planned = Vec::new();
+    let mut manifest = Vec::new();
+    let mut total_bytes: u64 = 0;
+    let mut total_tokens: u64 = 0;
+    let mut budget_hit = false;
+    for (group, path, content) in ordered {
+        if budget_hit {
+            manifest.push(ManifestEntry {
+                path: path.clone(),
+                bytes: 0,
+                tokens: 0,
+                status: ManifestStatus::Skipped,
+            });
+            planned.push((group, path, PlannedContent::Skipped));
+            continue;
+        }
+        match content {
+            FileContent::Text(text) => {
+                let bytes = text.len() as u64;
+                let chars = text.chars().count() as u64;
+                let tokens = estimate_tokens(chars);
+                if !budget_exceeded(total_bytes, total_tokens, bytes, tokens, max_bytes, max_tokens)
+                {
+                    total_bytes += bytes;
+                    total_tokens += tokens;
+                    manifest.push(ManifestEntry {
+                        path: path.clone(),
+                        bytes,
+                        tokens,
+                        status: ManifestStatus::Included,
+                    });
+                    planned.push((group, path, PlannedContent::Text(text)));
+                } else if matches!(on_overflow, OverflowMode::Truncate) {
+                    let truncated =
+                        truncate_to_budget(&text, total_bytes, total_tokens, max_bytes, max_tokens);
+                    let kept_bytes = truncated.len() as u64;
+                    let kept_tokens = estimate_tokens(truncated.chars().count() as u64);
+                    total_bytes += kept_bytes;
+                    total_tokens += kept_tokens;
+                    manifest.push(ManifestEntry {
+                        path: path.clone(),
+                        bytes: kept_bytes,
+                        tokens: kept_tokens,
+                        status: ManifestStatus::Truncated,
+                    });
+                    planned.push((group, path, PlannedContent::Text(truncated)));
+                    budget_hit = true;
+                } else {
+                    manifest.push(ManifestEntry {
+                        path: path.clone(),
+                        bytes: 0,
+                        tokens: 0,
+                        status: ManifestStatus::Skipped,
+                    });
+                    planned.push((group, path, PlannedContent::Skipped));
+                    budget_hit = true;
+                }
+            }
+            FileContent::Binary { bytes, mime } => {
+                let encoded_len = bytes.len().div_ceil(3) as u64 * 4;
+                let tokens = estimate_tokens(encoded_len);
+                if !budget_exceeded(
+                    total_bytes,
+                    total_tokens,
+                    encoded_len,
+                    tokens,
+                    max_bytes,
+                    max_tokens,
+                ) {
+                    total_bytes += encoded_len;
+                    total_tokens += tokens;
+                    manifest.push(ManifestEntry {
+                        path: path.clone(),
+                        bytes: encoded_len,
+                        tokens,
+                        status: ManifestStatus::Included,
+                    });
+                    planned.push((group, path, PlannedContent::Binary { bytes, mime }));
+                } else {
+                    // Base64 can't be truncated without corrupting it, so a
+                    // binary file always just skips on overflow.
+                    manifest.push(ManifestEntry {
+                        path: path.clone(),
+                        bytes: 0,
+                        tokens: 0,
+                        status: ManifestStatus::Skipped,
+                    });
+                    planned.push((group, path, PlannedContent::Skipped));
+                    budget_hit = true;
+                }
+            }
+        }
+    }
+    (planned, manifest)
+}
+
+fn manifest_status_str(status: &ManifestStatus) -> &'static str {
+    match status {
+        ManifestStatus::Included => "included",
+        ManifestStatus::Truncated => "truncated",
+        ManifestStatus::Skipped => "skipped",
+    }
+}
+
+fn write_manifest(writer: &mut dyn Write, manifest: &[ManifestEntry], text: bool) -> io::Result<()> {
+    if text {
+        writeln!(writer, "# Manifest")?;
+        for entry in manifest {
+            writeln!(
+                writer,
+                "# {} bytes={} tokens={} status={}",
+                entry.path.display(),
+                entry.bytes,
+                entry.tokens,
+                manifest_status_str(&entry.status)
+            )?;
+        }
+    } else {
+        writeln!(writer, "<manifest>")?;
+        for entry in manifest {
+            writeln!(
+                writer,
+                "<entry path=\"{}\" bytes=\"{}\" tokens=\"{}\" status=\"{}\"/>",
+                escape_xml(&entry.path.display().to_string()),
+                entry.bytes,
+                entry.tokens,
+                manifest_status_str(&entry.status)
+            )?;
+        }
+        writeln!(writer, "</manifest>")?;
+    }
+    Ok(())
+}
+
+/// Split `text` into `(content, terminator)` pairs, where `terminator` is
+/// `""`, `"\n"`, or `"\r\n"` — the building block both line-ending
+/// normalization and trailing-whitespace stripping operate on.
+fn split_lines_keep_ends(text: &str) -> Vec<(&str, &str)> {
+    let mut lines = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        match rest.find('\n') {
+            Some(idx) => {
+                let (line, remainder) = rest.split_at(idx + 1);
+                let term_start = if line.len() >= 2 && line.as_bytes()[line.len() - 2] == b'\r' {
+                    line.len() - 2
+                } else {
+                    line.len() - 1
+                };
+                lines.push((&line[..term_start], &line[term_start..]));
+                rest = remainder;
+            }
+            None => {
+                lines.push((rest, ""));
+                rest = "";
+            }
+        }
+    }
+    lines
+}
+
+/// Detect whichever of CRLF/LF appears more often among a file's own line
+/// endings, so `--line-endings preserve` can flatten stray mixed-in ones.
+fn detect_dominant_ending(text: &str) -> &'static str {
+    let crlf = text.matches("\r\n").count();
+    let lf_only = text.matches('\n').count().saturating_sub(crlf);
+    if crlf > lf_only {
+        "\r\n"
+    } else {
+        "\n"
+    }
+}
+
+/// Rewrite every line ending in `text` to the requested style.
+fn normalize_line_endings(text: &str, style: LineEndingStyle) -> String {
+    let target = match style {
+        LineEndingStyle::Lf => "\n",
+        LineEndingStyle::Crlf => "\r\n",
+        LineEndingStyle::Preserve => detect_dominant_ending(text),
+    };
+    split_lines_keep_ends(text)
+        .into_iter()
+        .map(|(content, term)| format!("{}{}", content, if term.is_empty() { "" } else { target }))
+        .collect()
+}
+
+/// Strip trailing spaces/tabs from every line, leaving each line's own
+/// terminator untouched.
+fn strip_trailing_ws(text: &str) -> String {
+    split_lines_keep_ends(text)
+        .into_iter()
+        .map(|(content, term)| format!("{}{}", content.trim_end_matches([' ', '\t']), term))
+        .collect()
+}
+
+/// Append a single trailing newline if `text` doesn't already end with one,
+/// matching whichever line ending the file already uses (CRLF if that's
+/// what's present, LF otherwise).
+fn ensure_final_newline(mut text: String) -> String {
+    if text.is_empty() || text.ends_with('\n') {
+        return text;
+    }
+    let ending = if text.contains("\r\n") { "\r\n" } else { "\n" };
+    text.push_str(ending);
+    text
+}
+
+/// Apply the `--line-endings`/`--strip-trailing-ws`/`--tabs-to-spaces`/
+/// `--ensure-final-newline` transforms to a text file's content, in an order
+/// where each later transform can rely on the ones before it: normalize
+/// endings first so line splitting is reliable, then strip trailing
+/// whitespace per line, then expand tabs, then top off the final newline.
+fn apply_transforms(mut text: String, opts: &Options) -> String {
+    if let Some(style) = opts.line_endings {
+        text = normalize_line_endings(&text, style);
+    }
+    if opts.strip_trailing_ws {
+        text = strip_trailing_ws(&text);
+    }
+    if let Some(n) = opts.tabs_to_spaces {
+        text = text.replace('\t', &" ".repeat(n));
+    }
+    if opts.ensure_final_newline {
+        text = ensure_final_newline(text);
+    }
+    text
+}
+
 fn main() -> io::Result<()> {
-    let mut opts = Options::default();
+    let mut opts = Options {
+        gitignore: true,
+        ..Options::default()
+    };
     let mut args = env::args().skip(1).peekable();
     while let Some(arg) = args.next() {
         match arg.as_str() {
@@ -163,6 +1222,51 @@ fn main() -> io::Result<()> {
             "-t" | "--tree" => opts.tree = true,
             "--text" => opts.text = true,
             "--xml" => opts.text = false,
+            "--no-ignore" => opts.gitignore = false,
+            "--gitignore" => opts.gitignore = true,
+            "--sort" => {
+                if let Some(val) = args.next() {
+                    opts.sort = parse_sort_key(&val);
+                }
+            }
+            "--group" => {
+                if let Some(val) = args.next() {
+                    if let Some(idx) = val.find("=>") {
+                        let pattern = val[..idx].trim().to_string();
+                        let template = val[idx + 2..].trim().to_string();
+                        opts.groups.push((pattern, template));
+                    }
+                }
+            }
+            "--max-bytes" => {
+                if let Some(val) = args.next() {
+                    opts.max_bytes = val.parse().ok();
+                }
+            }
+            "--max-tokens" => {
+                if let Some(val) = args.next() {
+                    opts.max_tokens = val.parse().ok();
+                }
+            }
+            "--on-overflow" => {
+                if let Some(val) = args.next() {
+                    if let Some(mode) = parse_overflow_mode(&val) {
+                        opts.on_overflow = mode;
+                    }
+                }
+            }
+            "--line-endings" => {
+                if let Some(val) = args.next() {
+                    opts.line_endings = parse_line_ending_style(&val);
+                }
+            }
+            "--strip-trailing-ws" => opts.strip_trailing_ws = true,
+            "--ensure-final-newline" => opts.ensure_final_newline = true,
+            "--tabs-to-spaces" => {
+                if let Some(val) = args.next() {
+                    opts.tabs_to_spaces = val.parse().ok();
+                }
+            }
             "-o" | "--output" => {
                 if let Some(val) = args.next() {
                     opts.output = Some(val);
@@ -181,9 +1285,10 @@ fn main() -> io::Result<()> {
     let mut files = Vec::new();
     for inp in &opts.inputs {
         let p = Path::new(inp);
-        gather_files(p, &mut files, &opts)?;
+        let mut ignore_stack = Vec::new();
+        gather_files(p, &mut files, &opts, &mut ignore_stack)?;
     }
-    let out_name = opts.output.unwrap_or_else(|| {
+    let out_name = opts.output.clone().unwrap_or_else(|| {
         if opts.text {
             "_concat-output.txt".to_string()
         } else {
@@ -192,20 +1297,91 @@ fn main() -> io::Result<()> {
     });
     let file = File::create(&out_name)?;
     let mut writer = BufWriter::new(file);
+    let buckets = build_buckets(&files, &opts.groups, opts.sort);
+    let mut ordered = Vec::new();
+    for bucket in &buckets {
+        for f in &bucket.files {
+            let content = match read_file_content(f)? {
+                FileContent::Text(text) => FileContent::Text(apply_transforms(text, &opts)),
+                binary => binary,
+            };
+            ordered.push((bucket.name.clone(), f.clone(), content));
+        }
+    }
+    let has_budget = opts.max_bytes.is_some() || opts.max_tokens.is_some();
+    let (planned, manifest) =
+        apply_budget(ordered, opts.max_bytes, opts.max_tokens, opts.on_overflow);
     if !opts.text {
         writeln!(writer, "<files>")?;
     }
-    for f in &files {
-        let content = fs::read_to_string(f)?;
-        if opts.text {
-            writeln!(writer, "{}", content)?;
-        } else {
-            writeln!(
-                writer,
-                "<file path=\"{}\"><![CDATA[{}]]></file>",
-                f.display(),
-                escape_xml(&content)
-            )?;
+    if has_budget {
+        write_manifest(&mut writer, &manifest, opts.text)?;
+    }
+    // A group whose every planned entry was skipped by the budget has
+    // nothing to show; suppress its wrapper instead of emitting an empty
+    // `<group>`/`Group:` shell.
+    let mut group_has_content: HashMap<Option<String>, bool> = HashMap::new();
+    for (group, _, content) in &planned {
+        let visible = !matches!(content, PlannedContent::Skipped);
+        let entry = group_has_content.entry(group.clone()).or_insert(false);
+        *entry = *entry || visible;
+    }
+    let mut current_group: Option<Option<String>> = None;
+    let mut current_group_suppressed = false;
+    for (group, f, content) in &planned {
+        if current_group.as_ref() != Some(group) {
+            if let Some(Some(_)) = &current_group {
+                if !opts.text && !current_group_suppressed {
+                    writeln!(writer, "</group>")?;
+                }
+            }
+            let suppressed =
+                group.is_some() && !group_has_content.get(group).copied().unwrap_or(false);
+            if let Some(name) = group {
+                if !suppressed {
+                    if opts.text {
+                        writeln!(writer, "Group: {}", name)?;
+                    } else {
+                        writeln!(writer, "<group name=\"{}\">", escape_xml(name))?;
+                    }
+                }
+            }
+            current_group = Some(group.clone());
+            current_group_suppressed = suppressed;
+        }
+        match content {
+            PlannedContent::Text(content) => {
+                if opts.text {
+                    writeln!(writer, "{}", content)?;
+                } else {
+                    writeln!(
+                        writer,
+                        "<file path=\"{}\"><![CDATA[{}]]></file>",
+                        f.display(),
+                        escape_xml(content)
+                    )?;
+                }
+            }
+            PlannedContent::Binary { bytes, mime } => {
+                let encoded = base64_encode(bytes);
+                if opts.text {
+                    writeln!(writer, "data:{};base64,{}", mime, encoded)?;
+                } else {
+                    writeln!(
+                        writer,
+                        "<file path=\"{}\" encoding=\"base64\" mime=\"{}\">{}</file>",
+                        f.display(),
+                        mime,
+                        encoded
+                    )?;
+                }
+            }
+            PlannedContent::Skipped => {}
+        }
+    }
+    if let Some(Some(_)) = &current_group {
+        if !opts.text && !current_group_suppressed {
+            writeln!(writer, "</group>")?;
         }
     }
     if opts.tree {
@@ -230,9 +1406,217 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_matches_pattern() {
-        assert!(matches_pattern("*.rs", "src/main.rs"));
-        assert!(matches_pattern("src/*", "src/main.rs"));
-        assert!(!matches_pattern("src/*.rs", "tests/main.rs"));
+    fn test_glob_match_star_and_question() {
+        assert!(glob_match("*.rs", "main.rs"));
+        assert!(!glob_match("*.rs", "src/main.rs"));
+        assert!(glob_match("src/*", "src/main.rs"));
+        assert!(!glob_match("src/*.rs", "tests/main.rs"));
+        assert!(glob_match("ma?n.rs", "main.rs"));
+        assert!(!glob_match("ma?n.rs", "maain.rs"));
+    }
+
+    #[test]
+    fn test_glob_match_double_star() {
+        assert!(glob_match("**/*.rs", "src/nested/main.rs"));
+        assert!(glob_match("**/*.rs", "main.rs"));
+        assert!(glob_match("src/**", "src/a/b/c.rs"));
+        assert!(!glob_match("src/**/c.rs", "src/a/b/d.rs"));
+        assert!(glob_match("src/**/c.rs", "src/a/b/c.rs"));
+    }
+
+    #[test]
+    fn test_glob_match_character_class() {
+        assert!(glob_match("file[0-9].txt", "file3.txt"));
+        assert!(!glob_match("file[0-9].txt", "filea.txt"));
+        assert!(glob_match("file[!0-9].txt", "filea.txt"));
+        assert!(!glob_match("file[!0-9].txt", "file3.txt"));
+        assert!(glob_match("[abc].rs", "b.rs"));
+    }
+
+    #[test]
+    fn test_glob_match_brace_expansion() {
+        assert!(glob_match("*.{rs,toml}", "main.rs"));
+        assert!(glob_match("*.{rs,toml}", "Cargo.toml"));
+        assert!(!glob_match("*.{rs,toml}", "README.md"));
+        assert!(glob_match("src/{a,b}/main.rs", "src/b/main.rs"));
+    }
+
+    #[test]
+    fn test_regex_full_match_captures() {
+        let caps = regex_full_match(r"ch(\d\d)-\d\d-.*\.md", "ch01-02-intro.md").unwrap();
+        assert_eq!(caps, vec![Some("01".to_string())]);
+        assert!(regex_full_match(r"ch(\d\d)-\d\d-.*\.md", "not-a-chapter.md").is_none());
+    }
+
+    #[test]
+    fn test_render_template() {
+        let caps = vec![Some("01".to_string())];
+        assert_eq!(render_template("chapter$1", &caps), "chapter01");
+        assert_eq!(render_template("no-placeholder", &caps), "no-placeholder");
+    }
+
+    #[test]
+    fn test_build_buckets_groups_and_orders() {
+        let files = vec![
+            PathBuf::from("ch01-02-intro.md"),
+            PathBuf::from("ch01-01-overview.md"),
+            PathBuf::from("ch02-01-next.md"),
+            PathBuf::from("README.md"),
+        ];
+        let groups = vec![(r"ch(\d\d)-\d\d-.*\.md".to_string(), "chapter$1".to_string())];
+        let buckets = build_buckets(&files, &groups, None);
+        assert_eq!(buckets.len(), 3);
+        assert_eq!(buckets[0].name, Some("chapter01".to_string()));
+        assert_eq!(
+            buckets[0].files,
+            vec![
+                PathBuf::from("ch01-01-overview.md"),
+                PathBuf::from("ch01-02-intro.md"),
+            ]
+        );
+        assert_eq!(buckets[1].name, Some("chapter02".to_string()));
+        assert_eq!(buckets[2].name, None);
+        assert_eq!(buckets[2].files, vec![PathBuf::from("README.md")]);
+    }
+
+    #[test]
+    fn test_apply_budget_skip_on_overflow() {
+        let ordered = vec![
+            (None, PathBuf::from("a.txt"), FileContent::Text("12345".to_string())),
+            (None, PathBuf::from("b.txt"), FileContent::Text("67890".to_string())),
+        ];
+        let (planned, manifest) = apply_budget(ordered, Some(5), None, OverflowMode::Skip);
+        assert!(matches!(planned[0].2, PlannedContent::Text(ref s) if s == "12345"));
+        assert!(matches!(planned[1].2, PlannedContent::Skipped));
+        assert!(matches!(manifest[0].status, ManifestStatus::Included));
+        assert!(matches!(manifest[1].status, ManifestStatus::Skipped));
+    }
+
+    #[test]
+    fn test_apply_budget_truncate_on_overflow() {
+        let ordered = vec![(
+            None,
+            PathBuf::from("a.txt"),
+            FileContent::Text("1234567890".to_string()),
+        )];
+        let (planned, manifest) = apply_budget(ordered, Some(4), None, OverflowMode::Truncate);
+        assert!(matches!(planned[0].2, PlannedContent::Text(ref s) if s == "1234"));
+        assert!(matches!(manifest[0].status, ManifestStatus::Truncated));
+        assert_eq!(manifest[0].bytes, 4);
+    }
+
+    #[test]
+    fn test_apply_budget_counts_utf8_bytes_not_chars() {
+        let ordered = vec![(
+            None,
+            PathBuf::from("greek.txt"),
+            FileContent::Text("\u{3b1}\u{3b2}\u{3b3}\n".to_string()),
+        )];
+        let (_, manifest) = apply_budget(ordered, Some(100), None, OverflowMode::Skip);
+        assert_eq!(manifest[0].bytes, 7);
+    }
+
+    #[test]
+    fn test_normalize_line_endings() {
+        assert_eq!(
+            normalize_line_endings("a\r\nb\nc\r\n", LineEndingStyle::Lf),
+            "a\nb\nc\n"
+        );
+        assert_eq!(
+            normalize_line_endings("a\nb\n", LineEndingStyle::Crlf),
+            "a\r\nb\r\n"
+        );
+        assert_eq!(
+            normalize_line_endings("a\r\nb\r\nc\n", LineEndingStyle::Preserve),
+            "a\r\nb\r\nc\r\n"
+        );
+    }
+
+    #[test]
+    fn test_strip_trailing_ws() {
+        assert_eq!(strip_trailing_ws("a  \nb\t\nc"), "a\nb\nc");
+    }
+
+    #[test]
+    fn test_ensure_final_newline() {
+        assert_eq!(ensure_final_newline("a".to_string()), "a\n");
+        assert_eq!(ensure_final_newline("a\n".to_string()), "a\n");
+        assert_eq!(ensure_final_newline("a\r\n".to_string()), "a\r\n");
+        assert_eq!(ensure_final_newline("".to_string()), "");
+    }
+
+    #[test]
+    fn test_apply_transforms_combination() {
+        let opts = Options {
+            line_endings: Some(LineEndingStyle::Lf),
+            strip_trailing_ws: true,
+            ensure_final_newline: true,
+            tabs_to_spaces: Some(2),
+            ..Options::default()
+        };
+        let result = apply_transforms("a\tx\r\nb  \r\n".to_string(), &opts);
+        assert_eq!(result, "a  x\nb\n");
+    }
+
+    #[test]
+    fn test_looks_binary() {
+        assert!(looks_binary(b"hello\0world"));
+        assert!(!looks_binary(b"hello world"));
+    }
+
+    #[test]
+    fn test_base64_encode() {
+        assert_eq!(base64_encode(b"hi"), "aGk=");
+        assert_eq!(base64_encode(b"hello"), "aGVsbG8=");
+        assert_eq!(base64_encode(b""), "");
+    }
+
+    #[test]
+    fn test_ignore_pattern_matches() {
+        let base = PathBuf::from("/repo");
+        let pattern = IgnorePattern {
+            glob: "target".to_string(),
+            negate: false,
+            dir_only: false,
+            anchored: false,
+            base: base.clone(),
+        };
+        assert!(ignore_pattern_matches(&pattern, &base.join("target"), true));
+        assert!(ignore_pattern_matches(
+            &pattern,
+            &base.join("nested/target"),
+            true
+        ));
+        assert!(!ignore_pattern_matches(&pattern, &base.join("target.rs"), true));
+    }
+
+    #[test]
+    fn test_is_ignored_negation_overrides() {
+        let base = PathBuf::from("/repo");
+        let patterns = vec![
+            IgnorePattern {
+                glob: "*.log".to_string(),
+                negate: false,
+                dir_only: false,
+                anchored: false,
+                base: base.clone(),
+            },
+            IgnorePattern {
+                glob: "keep.log".to_string(),
+                negate: true,
+                dir_only: false,
+                anchored: false,
+                base: base.clone(),
+            },
+        ];
+        let stack = vec![patterns];
+        assert!(is_ignored(&stack, &base.join("debug.log"), false));
+        assert!(!is_ignored(&stack, &base.join("keep.log"), false));
+    }
+
+    #[test]
+    fn test_mime_for_ext() {
+        assert_eq!(mime_for_ext("png"), "image/png");
+        assert_eq!(mime_for_ext("bin"), "application/octet-stream");
     }
 }